@@ -18,7 +18,11 @@ use crate::{
 
 /// Desktop Output
 ///
-/// Instead of streaming, we run a ffplay instance and play on desktop.
+/// Instead of streaming, we run a ffplay instance and play on desktop. A
+/// desktop window can only show one rendition at a time, so when the channel
+/// declares an ABR ladder in `config.output.variants` we deliberately only
+/// preview the first (typically highest-quality) variant's resolution
+/// instead of building the full multi-output split filtergraph.
 pub async fn output(config: &PlayoutConfig, log_format: &str) -> Result<Child, ServiceError> {
     let id = config.general.channel_id;
     let mut enc_prefix = vec_strings!["-hide_banner", "-nostats", "-v", log_format];
@@ -28,6 +32,17 @@ pub async fn output(config: &PlayoutConfig, log_format: &str) -> Result<Child, S
     };
     media.add_filter(config, &None).await;
 
+    if let Some(variant) = config.output.variants.first() {
+        debug!(target: Target::file_mail(), channel = id;
+            "ABR ladder configured with {} variants; desktop preview only shows \"{}\"",
+            config.output.variants.len(),
+            variant.name
+        );
+        media
+            .filter
+            .push(format!("scale={}:{}", variant.width, variant.height));
+    }
+
     if let Some(input_cmd) = &config.advanced.encoder.input_cmd {
         enc_prefix.append(&mut input_cmd.clone());
     }
@@ -11,6 +11,7 @@ use crate::vec_strings;
 use crate::{
     player::{
         controller::ProcessUnit::*,
+        output::variants::prepare_variant_cmd,
         utils::{Media, insert_readrate, prepare_output_cmd},
     },
     utils::errors::ServiceError,
@@ -18,7 +19,10 @@ use crate::{
 
 /// Streaming Output
 ///
-/// Prepare the ffmpeg command for streaming output
+/// Prepare the ffmpeg command for streaming output. When the channel
+/// declares an ABR bitrate ladder in `config.output.variants`, a single
+/// decode feeds a `split` filtergraph that fans out into every rendition;
+/// otherwise a single ffmpeg encoder is spawned as before.
 pub async fn output(config: &PlayoutConfig, log_format: &str) -> Result<Child, ServiceError> {
     let id = config.general.channel_id;
     let mut enc_prefix = vec_strings!["-hide_banner", "-nostats", "-v", log_format];
@@ -36,7 +40,11 @@ pub async fn output(config: &PlayoutConfig, log_format: &str) -> Result<Child, S
 
     insert_readrate(&config.general.ffmpeg_options, &mut enc_prefix, 1.0);
 
-    let enc_cmd = prepare_output_cmd(config, enc_prefix, &media.filter);
+    let enc_cmd = if config.output.variants.is_empty() {
+        prepare_output_cmd(config, enc_prefix, &media.filter)
+    } else {
+        prepare_variant_cmd(enc_prefix, &media.filter, &config.output.variants)
+    };
 
     debug!(target: Target::file_mail(), channel = id;
         "Encoder CMD: <span class=\"log-cmd\">ffmpeg {}</span>",
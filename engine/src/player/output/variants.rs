@@ -0,0 +1,208 @@
+use serde::{Deserialize, Serialize};
+
+use crate::vec_strings;
+
+/// One rendition of a multi-variant (ABR) output.
+///
+/// A channel can declare an ordered list of these; `output()` turns them into
+/// a single ffmpeg invocation that decodes once and encodes every variant in
+/// parallel via a `split` filtergraph, instead of spawning one ffmpeg process
+/// per rendition.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct OutputVariant {
+    /// Short, filename/playlist safe identifier, e.g. `"1080p"`.
+    pub name: String,
+    pub width: i64,
+    pub height: i64,
+    pub video_bitrate: String,
+    pub video_buf_size: String,
+    pub audio_bitrate: String,
+    /// Video codec, falls back to `libx264` if empty.
+    #[serde(default)]
+    pub video_codec: String,
+    /// Destination for this rendition. When every variant's `target` contains
+    /// a `%v` placeholder, all variants are muxed through a single HLS
+    /// `-var_stream_map`/`-master_pl_name` output (one shared master + variant
+    /// playlists). Otherwise each variant is written as its own independent
+    /// ffmpeg output (distinct RTMP targets, one file per rendition, ...).
+    pub target: String,
+}
+
+/// Build the `-filter_complex split[...]` graph plus per-variant `-map`/codec
+/// args for an ordered list of [`OutputVariant`]s, and append the resulting
+/// output(s) to `enc_prefix`.
+///
+/// `enc_prefix` must already contain the decoder input (`-i pipe:0` and
+/// friends); `video_filter` is whatever filtergraph `Media::add_filter`
+/// produced for the single decode stage.
+pub fn prepare_variant_cmd(
+    mut enc_prefix: Vec<String>,
+    video_filter: &[String],
+    variants: &[OutputVariant],
+) -> Vec<String> {
+    enc_prefix.append(&mut vec_strings![
+        "-filter_complex",
+        split_filter_complex(video_filter, variants)
+    ]);
+
+    if variants.iter().all(|v| v.target.contains("%v")) {
+        append_hls_ladder_output(&mut enc_prefix, variants);
+    } else {
+        append_per_variant_outputs(&mut enc_prefix, variants);
+    }
+
+    enc_prefix
+}
+
+/// `[<video_filter>;]split=N[s0][s1]...;[s0]scale=w:h[v0];[s1]scale=w:h[v1];...`
+fn split_filter_complex(video_filter: &[String], variants: &[OutputVariant]) -> String {
+    let mut graph = String::new();
+
+    if !video_filter.is_empty() {
+        graph.push_str(&video_filter.join(""));
+        graph.push(';');
+    }
+
+    let splits: Vec<String> = (0..variants.len()).map(|i| format!("[s{i}]")).collect();
+    graph.push_str(&format!("split={}{}", variants.len(), splits.join("")));
+
+    for (i, variant) in variants.iter().enumerate() {
+        graph.push_str(&format!(
+            ";[s{i}]scale={}:{}[v{i}]",
+            variant.width, variant.height
+        ));
+    }
+
+    graph
+}
+
+fn video_codec(variant: &OutputVariant) -> String {
+    if variant.video_codec.is_empty() {
+        "libx264".to_string()
+    } else {
+        variant.video_codec.clone()
+    }
+}
+
+/// One shared HLS output: every variant's `-map`/codec options land on the
+/// stream-specifier-suffixed (`:i`) options of a single muxer, selected by
+/// `-var_stream_map`, with one `%v`-patterned target.
+fn append_hls_ladder_output(enc_prefix: &mut Vec<String>, variants: &[OutputVariant]) {
+    for (i, variant) in variants.iter().enumerate() {
+        enc_prefix.append(&mut vec_strings![
+            "-map",
+            format!("[v{i}]"),
+            "-map",
+            "0:a",
+            format!("-c:v:{i}"),
+            video_codec(variant),
+            format!("-b:v:{i}"),
+            variant.video_bitrate.clone(),
+            format!("-bufsize:v:{i}"),
+            variant.video_buf_size.clone(),
+            format!("-c:a:{i}"),
+            "aac",
+            format!("-b:a:{i}"),
+            variant.audio_bitrate.clone()
+        ]);
+    }
+
+    enc_prefix.append(&mut vec_strings![
+        "-var_stream_map",
+        variant_stream_map(variants),
+        "-master_pl_name",
+        "master.m3u8",
+        "-f",
+        "hls"
+    ]);
+
+    enc_prefix.push(variants[0].target.clone());
+}
+
+/// Independent outputs: each variant gets its own `-map`/codec options
+/// immediately followed by its own target, exactly like chaining several
+/// single-rendition ffmpeg outputs after one shared input/decode.
+fn append_per_variant_outputs(enc_prefix: &mut Vec<String>, variants: &[OutputVariant]) {
+    for (i, variant) in variants.iter().enumerate() {
+        enc_prefix.append(&mut vec_strings![
+            "-map",
+            format!("[v{i}]"),
+            "-map",
+            "0:a",
+            "-c:v",
+            video_codec(variant),
+            "-b:v",
+            variant.video_bitrate.clone(),
+            "-bufsize:v",
+            variant.video_buf_size.clone(),
+            "-c:a",
+            "aac",
+            "-b:a",
+            variant.audio_bitrate.clone()
+        ]);
+
+        if variant.target.starts_with("rtmp://") || variant.target.starts_with("rtmps://") {
+            enc_prefix.append(&mut vec_strings!["-f", "flv"]);
+        }
+
+        enc_prefix.push(variant.target.clone());
+    }
+}
+
+fn variant_stream_map(variants: &[OutputVariant]) -> String {
+    variants
+        .iter()
+        .enumerate()
+        .map(|(i, v)| format!("v:{i},a:{i},name:{}", v.name))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variant(name: &str, target: &str) -> OutputVariant {
+        OutputVariant {
+            name: name.to_string(),
+            width: 1920,
+            height: 1080,
+            video_bitrate: "5000k".to_string(),
+            video_buf_size: "10000k".to_string(),
+            audio_bitrate: "192k".to_string(),
+            video_codec: String::new(),
+            target: target.to_string(),
+        }
+    }
+
+    #[test]
+    fn hls_ladder_shares_one_output_with_var_stream_map() {
+        let variants = vec![
+            variant("1080p", "hls/variant_%v/stream.m3u8"),
+            variant("720p", "hls/variant_%v/stream.m3u8"),
+        ];
+
+        let cmd = prepare_variant_cmd(vec!["-i".into(), "pipe:0".into()], &[], &variants);
+
+        assert!(cmd.contains(&"-var_stream_map".to_string()));
+        assert_eq!(
+            cmd.iter().filter(|a| *a == "hls/variant_%v/stream.m3u8").count(),
+            1,
+            "the %v pattern must appear exactly once as a shared output"
+        );
+    }
+
+    #[test]
+    fn distinct_targets_become_independent_outputs() {
+        let variants = vec![
+            variant("1080p", "rtmp://server/app/1080p"),
+            variant("720p", "rtmp://server/app/720p"),
+        ];
+
+        let cmd = prepare_variant_cmd(vec!["-i".into(), "pipe:0".into()], &[], &variants);
+
+        assert!(!cmd.contains(&"-var_stream_map".to_string()));
+        assert!(cmd.contains(&"rtmp://server/app/1080p".to_string()));
+        assert!(cmd.contains(&"rtmp://server/app/720p".to_string()));
+    }
+}
@@ -1,23 +1,24 @@
-use std::{
-    io::Write,
-    path::{Path, PathBuf},
-};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use actix_multipart::Multipart;
 use actix_web::{web, HttpResponse};
-use futures_util::TryStreamExt as _;
-use lazy_static::lazy_static;
+use futures_util::{stream, StreamExt, TryStreamExt as _};
 use lexical_sort::{natural_lexical_cmp, PathSort};
 use rand::{distributions::Alphanumeric, Rng};
-use relative_path::RelativePath;
 use serde::{Deserialize, Serialize};
 use sqlx::{Pool, Sqlite};
-use tokio::fs;
 
 use simplelog::*;
 
-use crate::utils::{errors::ServiceError, playout_config};
-use ffplayout_lib::utils::{file_extension, MediaProbe};
+use crate::utils::errors::ServiceError;
+use crate::utils::index::{self, IndexRow};
+use crate::utils::playout_config;
+use crate::utils::probe::probe_media;
+use crate::utils::store::{build_store, Entry, Store};
+use ffplayout_lib::utils::file_extension;
+
+pub use crate::utils::store::local::norm_abs_path;
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct PathObject {
@@ -55,78 +56,79 @@ pub struct VideoFile {
     duration: f64,
 }
 
-lazy_static! {
-    pub static ref HOME_DIR: String = home::home_dir()
-        .unwrap_or("/home/h1wl3n2og".into()) // any random not existing folder
-        .as_os_str()
-        .to_string_lossy()
-        .to_string();
+/// Resolve the [`Store`] a channel's storage is configured with.
+async fn channel_store(conn: &Pool<Sqlite>, id: i32) -> Result<Arc<dyn Store>, ServiceError> {
+    let (config, _) = playout_config(conn, &id).await?;
+
+    Ok(build_store(
+        &config.storage.backend,
+        config.storage.path,
+        config.storage.remote_endpoint,
+        config.storage.remote_bucket,
+        config.storage.remote_region,
+    ))
 }
 
-const FOLDER_WHITELIST: &[&str; 6] = &[
-    "/media",
-    "/mnt",
-    "/playlists",
-    "/tv-media",
-    "/usr/share/ffplayout",
-    "/var/lib/ffplayout",
-];
+fn split_entries(entries: Vec<Entry>) -> (Vec<String>, Vec<String>) {
+    let mut folders = vec![];
+    let mut files = vec![];
 
-/// Normalize absolut path
-///
-/// This function takes care, that it is not possible to break out from root_path.
-pub fn norm_abs_path(
-    root_path: &Path,
-    input_path: &str,
-) -> Result<(PathBuf, String, String), ServiceError> {
-    let path_relative = RelativePath::new(&root_path.to_string_lossy())
-        .normalize()
-        .to_string()
-        .replace("../", "");
-    let mut source_relative = RelativePath::new(input_path)
-        .normalize()
-        .to_string()
-        .replace("../", "");
-    let path_suffix = root_path
-        .file_name()
-        .unwrap_or_default()
-        .to_string_lossy()
-        .to_string();
+    for entry in entries {
+        if entry.is_dir {
+            folders.push(entry.name);
+        } else {
+            files.push(entry.name);
+        }
+    }
+
+    folders.path_sort(natural_lexical_cmp);
+    files.path_sort(natural_lexical_cmp);
+
+    (folders, files)
+}
 
-    if input_path.starts_with(&*root_path.to_string_lossy())
-        || source_relative.starts_with(&path_relative)
+/// Serve a file's duration from the SQLite media index when the cached
+/// `size`/`mtime` still match the store, falling back to a direct probe
+/// (and opportunistically refreshing the cache) on a miss.
+async fn duration_from_index_or_probe(
+    conn: &Pool<Sqlite>,
+    channel_id: i32,
+    store: &Arc<dyn Store>,
+    path: &str,
+) -> Result<f64, ServiceError> {
+    let meta = store.head(path).await?;
+    let mtime = meta
+        .modified
+        .and_then(|m| m.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default();
+
+    if let Some(IndexRow {
+        size,
+        mtime: cached_mtime,
+        duration,
+        ..
+    }) = index::lookup(conn, channel_id, path).await?
     {
-        source_relative = source_relative
-            .strip_prefix(&path_relative)
-            .and_then(|s| s.strip_prefix('/'))
-            .unwrap_or_default()
-            .to_string();
-    } else {
-        source_relative = source_relative
-            .strip_prefix(&path_suffix)
-            .and_then(|s| s.strip_prefix('/'))
-            .unwrap_or(&source_relative)
-            .to_string();
+        if size == meta.size as i64 && cached_mtime == mtime {
+            return Ok(duration);
+        }
     }
 
-    let path = &root_path.join(&source_relative);
+    let duration = probe_media(store, path).await?.duration;
 
-    if !FOLDER_WHITELIST.iter().any(|f| path.starts_with(f))
-        && !path.starts_with(&HOME_DIR.to_string())
-    {
-        return Err(ServiceError::Forbidden(
-            "Access forbidden: Folder cannot be opened.".to_string(),
-        ));
+    if let Err(e) = index::index_file(conn, channel_id, store, path).await {
+        error!("refresh media index for {path}: {e}");
     }
 
-    Ok((path.to_path_buf(), path_suffix, source_relative))
+    Ok(duration)
 }
 
 /// File Browser
 ///
 /// Take input path and give file and folder list from it back.
-/// Input should be a relative path segment, but when it is a absolut path, the norm_abs_path function
-/// will take care, that user can not break out from given storage path in config.
+/// Input should be a relative path segment; the configured [`Store`] takes
+/// care of normalizing/jailing it per-backend.
 pub async fn browser(
     conn: &Pool<Sqlite>,
     id: i32,
@@ -138,94 +140,67 @@ pub async fn browser(
         .split(',')
         .map(|e| e.to_string())
         .collect::<Vec<String>>();
-    let mut parent_folders = vec![];
     let mut extensions = config.storage.extensions;
     extensions.append(&mut channel_extensions);
 
-    let (path, parent, path_component) = norm_abs_path(&config.storage.path, &path_obj.source)?;
-
-    let parent_path = if !path_component.is_empty() {
-        path.parent().unwrap()
-    } else {
-        &config.storage.path
-    };
+    let store = channel_store(conn, id).await?;
+    index::ensure_channel_indexed(conn.clone(), id, store.clone()).await;
+    let source = path_obj.source.clone();
+    let parent = source
+        .trim_end_matches('/')
+        .rsplit('/')
+        .nth(1)
+        .unwrap_or_default()
+        .to_string();
 
-    let mut obj = PathObject::new(path_component, Some(parent));
+    let mut obj = PathObject::new(source.clone(), Some(parent));
     obj.folders_only = path_obj.folders_only;
 
-    if path != parent_path && !path_obj.folders_only {
-        let mut parents = fs::read_dir(&parent_path).await?;
-
-        while let Some(child) = parents.next_entry().await? {
-            if child.metadata().await?.is_dir() {
-                parent_folders.push(
-                    child
-                        .path()
-                        .file_name()
-                        .unwrap()
-                        .to_string_lossy()
-                        .to_string(),
-                );
-            }
-        }
+    if !source.is_empty() && !path_obj.folders_only {
+        let grand_parent = source.trim_end_matches('/').rsplit_once('/').map(|s| s.0);
+        let mut parent_folders = store
+            .list(grand_parent.unwrap_or_default())
+            .await?
+            .into_iter()
+            .filter(|e| e.is_dir)
+            .map(|e| e.name)
+            .collect::<Vec<_>>();
 
         parent_folders.path_sort(natural_lexical_cmp);
-
         obj.parent_folders = Some(parent_folders);
     }
 
-    let mut paths_obj = fs::read_dir(path).await?;
-
-    let mut files = vec![];
-    let mut folders = vec![];
+    let entries = store.list(&source).await?;
+    let (folders, files) = split_entries(entries);
+    let mut media_files = vec![];
 
-    while let Some(child) = paths_obj.next_entry().await? {
-        let f_meta = child.metadata().await?;
+    if !path_obj.folders_only {
+        for file in files {
+            let full_path = format!("{}/{}", source.trim_end_matches('/'), file);
+            let probe_path = PathBuf::from(&full_path);
 
-        // ignore hidden files/folders on unix
-        if child.path().to_string_lossy().to_string().contains("/.") {
-            continue;
-        }
+            let Some(ext) = file_extension(&probe_path) else {
+                continue;
+            };
 
-        if f_meta.is_dir() {
-            folders.push(
-                child
-                    .path()
-                    .file_name()
-                    .unwrap()
-                    .to_string_lossy()
-                    .to_string(),
-            );
-        } else if f_meta.is_file() && !path_obj.folders_only {
-            if let Some(ext) = file_extension(&child.path()) {
-                if extensions.contains(&ext.to_string().to_lowercase()) {
-                    files.push(child.path())
-                }
+            if !extensions.contains(&ext.to_string().to_lowercase()) {
+                continue;
             }
-        }
-    }
-
-    folders.path_sort(natural_lexical_cmp);
-    files.path_sort(natural_lexical_cmp);
-    let mut media_files = vec![];
 
-    for file in files {
-        match MediaProbe::new(file.to_string_lossy().as_ref()) {
-            Ok(probe) => {
-                let mut duration = 0.0;
-
-                if let Some(dur) = probe.format.duration {
-                    duration = dur.parse().unwrap_or_default()
+            let duration = match duration_from_index_or_probe(conn, id, &store, &full_path).await
+            {
+                Ok(d) => d,
+                Err(e) => {
+                    error!("{e:?}");
+                    0.0
                 }
+            };
 
-                let video = VideoFile {
-                    name: file.file_name().unwrap().to_string_lossy().to_string(),
-                    duration,
-                };
-                media_files.push(video);
-            }
-            Err(e) => error!("{e:?}"),
-        };
+            media_files.push(VideoFile {
+                name: file,
+                duration,
+            });
+        }
     }
 
     obj.folders = Some(folders);
@@ -239,105 +214,52 @@ pub async fn create_directory(
     id: i32,
     path_obj: &PathObject,
 ) -> Result<HttpResponse, ServiceError> {
-    let (config, _) = playout_config(conn, &id).await?;
-    let (path, _, _) = norm_abs_path(&config.storage.path, &path_obj.source)?;
+    let store = channel_store(conn, id).await?;
 
-    if let Err(e) = fs::create_dir_all(&path).await {
+    if let Err(e) = store.create_folder(&path_obj.source).await {
         return Err(ServiceError::BadRequest(e.to_string()));
     }
 
-    info!(
-        "create folder: <b><magenta>{}</></b>",
-        path.to_string_lossy()
-    );
+    info!("create folder: <b><magenta>{}</></b>", path_obj.source);
 
     Ok(HttpResponse::Ok().into())
 }
 
-async fn copy_and_delete(source: &PathBuf, target: &PathBuf) -> Result<MoveObject, ServiceError> {
-    match fs::copy(&source, &target).await {
-        Ok(_) => {
-            if let Err(e) = fs::remove_file(source).await {
-                error!("{e}");
-                return Err(ServiceError::BadRequest(
-                    "Removing File not possible!".into(),
-                ));
-            };
-
-            return Ok(MoveObject {
-                source: source
-                    .file_name()
-                    .unwrap_or_default()
-                    .to_string_lossy()
-                    .to_string(),
-                target: target
-                    .file_name()
-                    .unwrap_or_default()
-                    .to_string_lossy()
-                    .to_string(),
-            });
-        }
-        Err(e) => {
-            error!("{e}");
-            Err(ServiceError::BadRequest("Error in file copy!".into()))
-        }
-    }
-}
-
-async fn rename(source: &PathBuf, target: &PathBuf) -> Result<MoveObject, ServiceError> {
-    match fs::rename(source, target).await {
-        Ok(_) => Ok(MoveObject {
-            source: source
-                .file_name()
-                .unwrap_or_default()
-                .to_string_lossy()
-                .to_string(),
-            target: target
-                .file_name()
-                .unwrap_or_default()
-                .to_string_lossy()
-                .to_string(),
-        }),
-        Err(e) => {
-            error!("{e}");
-            copy_and_delete(source, target).await
-        }
-    }
-}
-
 pub async fn rename_file(
     conn: &Pool<Sqlite>,
     id: i32,
     move_object: &MoveObject,
 ) -> Result<MoveObject, ServiceError> {
-    let (config, _) = playout_config(conn, &id).await?;
-    let (source_path, _, _) = norm_abs_path(&config.storage.path, &move_object.source)?;
-    let (mut target_path, _, _) = norm_abs_path(&config.storage.path, &move_object.target)?;
+    let store = channel_store(conn, id).await?;
 
-    if !source_path.exists() {
+    if !store.exists(&move_object.source).await {
         return Err(ServiceError::BadRequest("Source file not exist!".into()));
     }
 
-    if (source_path.is_dir() || source_path.is_file()) && source_path.parent() == Some(&target_path)
-    {
-        return rename(&source_path, &target_path).await;
-    }
+    let mut target = move_object.target.clone();
 
-    if target_path.is_dir() {
-        target_path = target_path.join(source_path.file_name().unwrap());
+    if store.is_dir(&target).await {
+        let file_name = move_object
+            .source
+            .trim_end_matches('/')
+            .rsplit('/')
+            .next()
+            .unwrap_or_default();
+        target = format!("{}/{file_name}", target.trim_end_matches('/'));
     }
 
-    if target_path.is_file() {
+    if store.exists(&target).await && !store.is_dir(&target).await {
         return Err(ServiceError::BadRequest(
             "Target file already exists!".into(),
         ));
     }
 
-    if source_path.is_file() && target_path.parent().is_some() {
-        return rename(&source_path, &target_path).await;
-    }
+    store.rename(&move_object.source, &target).await?;
 
-    Err(ServiceError::InternalServerError)
+    Ok(MoveObject {
+        source: move_object.source.clone(),
+        target,
+    })
 }
 
 pub async fn remove_file_or_folder(
@@ -345,47 +267,23 @@ pub async fn remove_file_or_folder(
     id: i32,
     source_path: &str,
 ) -> Result<(), ServiceError> {
-    let (config, _) = playout_config(conn, &id).await?;
-    let (source, _, _) = norm_abs_path(&config.storage.path, source_path)?;
+    let store = channel_store(conn, id).await?;
 
-    if !source.exists() {
+    if !store.exists(source_path).await {
         return Err(ServiceError::BadRequest("Source does not exists!".into()));
     }
 
-    if source.is_dir() {
-        match fs::remove_dir(source).await {
-            Ok(_) => return Ok(()),
-            Err(e) => {
-                error!("{e}");
-                return Err(ServiceError::BadRequest(
-                    "Delete folder failed! (Folder must be empty)".into(),
-                ));
-            }
-        };
-    }
-
-    if source.is_file() {
-        match fs::remove_file(source).await {
-            Ok(_) => return Ok(()),
-            Err(e) => {
-                error!("{e}");
-                return Err(ServiceError::BadRequest("Delete file failed!".into()));
-            }
-        };
-    }
-
-    Err(ServiceError::InternalServerError)
+    store.delete(source_path).await
 }
 
-async fn valid_path(conn: &Pool<Sqlite>, id: i32, path: &str) -> Result<PathBuf, ServiceError> {
-    let (config, _) = playout_config(conn, &id).await?;
-    let (test_path, _, _) = norm_abs_path(&config.storage.path, path)?;
+async fn valid_path(conn: &Pool<Sqlite>, id: i32, path: &str) -> Result<(), ServiceError> {
+    let store = channel_store(conn, id).await?;
 
-    if !test_path.is_dir() {
+    if !store.is_dir(path).await {
         return Err(ServiceError::BadRequest("Target folder not exists!".into()));
     }
 
-    Ok(test_path)
+    Ok(())
 }
 
 pub async fn upload(
@@ -396,6 +294,8 @@ pub async fn upload(
     path: &Path,
     abs_path: bool,
 ) -> Result<HttpResponse, ServiceError> {
+    let store = channel_store(conn, id).await?;
+
     while let Some(mut field) = payload.try_next().await? {
         let content_disposition = field.content_disposition();
         debug!("{content_disposition}");
@@ -408,47 +308,39 @@ pub async fn upload(
             .get_filename()
             .map_or_else(|| rand_string.to_string(), sanitize_filename::sanitize);
 
-        let filepath = if abs_path {
-            path.to_path_buf()
+        let target = if abs_path {
+            path.to_string_lossy().to_string()
         } else {
-            valid_path(conn, id, &path.to_string_lossy())
-                .await?
-                .join(filename)
-        };
-        let filepath_clone = filepath.clone();
-
-        let _file_size = match filepath.metadata() {
-            Ok(metadata) => metadata.len(),
-            Err(_) => 0,
+            valid_path(conn, id, &path.to_string_lossy()).await?;
+            format!("{}/{filename}", path.to_string_lossy().trim_end_matches('/'))
         };
 
         // INFO: File exist check should be enough because file size and content length are different.
         // The error catching in the loop should normally prevent unfinished files from existing on disk.
-        // If this is not enough, a second check can be implemented: is_close(file_size as i64, size as i64, 1000)
-        if filepath.is_file() {
+        if store.exists(&target).await {
             return Err(ServiceError::Conflict("Target already exists!".into()));
         }
 
-        let mut f = web::block(|| std::fs::File::create(filepath_clone)).await??;
-
-        loop {
+        let target_clone = target.clone();
+        let chunks = stream::unfold(field, |mut field| async move {
             match field.try_next().await {
-                Ok(Some(chunk)) => {
-                    f = web::block(move || f.write_all(&chunk).map(|_| f)).await??;
-                }
-
-                Ok(None) => break,
-
-                Err(e) => {
-                    if e.to_string().contains("stream is incomplete") {
-                        info!("Delete non finished file: {filepath:?}");
+                Ok(Some(chunk)) => Some((Ok(chunk), field)),
+                Ok(None) => None,
+                Err(e) => Some((Err(ServiceError::from(e)), field)),
+            }
+        })
+        .boxed();
 
-                        tokio::fs::remove_file(filepath).await?
-                    }
+        if let Err(e) = store.put(&target, chunks).await {
+            if e.to_string().contains("stream is incomplete") {
+                info!("Delete non finished file: {target_clone}");
 
-                    return Err(e.into());
+                if let Err(del_err) = store.delete(&target_clone).await {
+                    error!("{del_err}");
                 }
             }
+
+            return Err(e);
         }
     }
 
@@ -0,0 +1,344 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, Pool, Sqlite};
+use tokio::sync::{Mutex, RwLock};
+
+use simplelog::*;
+
+use crate::utils::errors::ServiceError;
+use crate::utils::probe::probe_media;
+use crate::utils::store::Store;
+
+pub mod watcher;
+
+/// Progress/lifecycle of one directory scan.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobState {
+    Queued,
+    Running,
+    Paused,
+    Done,
+}
+
+/// Reported progress of a running or finished index job, for the UI.
+#[derive(Debug, Clone, Serialize)]
+pub struct JobStatus {
+    pub channel_id: i32,
+    pub state: JobState,
+    pub percent: f32,
+    pub current_file: Option<String>,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct IndexRow {
+    pub path: String,
+    pub size: i64,
+    pub mtime: i64,
+    pub duration: f64,
+    pub probe_json: String,
+}
+
+/// Shared, in-memory view of the currently running/queued jobs, so the API
+/// can answer status queries without round-tripping to SQLite.
+#[derive(Default)]
+pub struct JobRegistry {
+    jobs: RwLock<Vec<JobStatus>>,
+}
+
+impl JobRegistry {
+    pub async fn set(&self, status: JobStatus) {
+        let mut jobs = self.jobs.write().await;
+
+        if let Some(existing) = jobs.iter_mut().find(|j| j.channel_id == status.channel_id) {
+            *existing = status;
+        } else {
+            jobs.push(status);
+        }
+    }
+
+    pub async fn get(&self, channel_id: i32) -> Option<JobStatus> {
+        self.jobs
+            .read()
+            .await
+            .iter()
+            .find(|j| j.channel_id == channel_id)
+            .cloned()
+    }
+}
+
+async fn ensure_index_table(conn: &Pool<Sqlite>) -> Result<(), ServiceError> {
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS media_index (
+            channel_id INTEGER NOT NULL,
+            path TEXT NOT NULL,
+            size INTEGER NOT NULL,
+            mtime INTEGER NOT NULL,
+            duration REAL NOT NULL,
+            probe_json TEXT NOT NULL,
+            PRIMARY KEY (channel_id, path)
+        )",
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Look up one cached row, if present. Whether it is still fresh
+/// (`size`/`mtime` unchanged) is the caller's responsibility to check.
+pub async fn lookup(
+    conn: &Pool<Sqlite>,
+    channel_id: i32,
+    path: &str,
+) -> Result<Option<IndexRow>, ServiceError> {
+    ensure_index_table(conn).await?;
+
+    let row = sqlx::query_as::<_, IndexRow>(
+        "SELECT path, size, mtime, duration, probe_json FROM media_index WHERE channel_id = ? AND path = ?",
+    )
+    .bind(channel_id)
+    .bind(path)
+    .fetch_optional(conn)
+    .await?;
+
+    Ok(row)
+}
+
+async fn upsert(
+    conn: &Pool<Sqlite>,
+    channel_id: i32,
+    path: &str,
+    size: u64,
+    mtime: i64,
+    duration: f64,
+    probe_json: &str,
+) -> Result<(), ServiceError> {
+    sqlx::query(
+        "INSERT INTO media_index (channel_id, path, size, mtime, duration, probe_json)
+         VALUES (?, ?, ?, ?, ?, ?)
+         ON CONFLICT(channel_id, path) DO UPDATE SET
+            size = excluded.size, mtime = excluded.mtime,
+            duration = excluded.duration, probe_json = excluded.probe_json",
+    )
+    .bind(channel_id)
+    .bind(path)
+    .bind(size as i64)
+    .bind(mtime)
+    .bind(duration)
+    .bind(probe_json)
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Invalidate (and let the next scan re-probe) a single cached path, e.g. in
+/// response to a watcher delete event.
+pub async fn invalidate(conn: &Pool<Sqlite>, channel_id: i32, path: &str) -> Result<(), ServiceError> {
+    ensure_index_table(conn).await?;
+
+    sqlx::query("DELETE FROM media_index WHERE channel_id = ? AND path = ?")
+        .bind(channel_id)
+        .bind(path)
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Probe and cache a single file, skipping the probe if size/mtime already
+/// match what is cached.
+pub async fn index_file(
+    conn: &Pool<Sqlite>,
+    channel_id: i32,
+    store: &Arc<dyn Store>,
+    path: &str,
+) -> Result<(), ServiceError> {
+    let meta = store.head(path).await?;
+    let mtime = meta
+        .modified
+        .and_then(|m| m.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default();
+
+    if let Some(existing) = lookup(conn, channel_id, path).await? {
+        if existing.size == meta.size as i64 && existing.mtime == mtime {
+            return Ok(());
+        }
+    }
+
+    let probe = probe_media(store, path).await?;
+    let duration = probe.duration;
+    let streams = probe
+        .streams
+        .iter()
+        .map(|s| {
+            serde_json::json!({
+                "codec_type": s.codec_type,
+                "codec_name": s.codec_name,
+                "width": s.width,
+                "height": s.height,
+            })
+        })
+        .collect::<Vec<_>>();
+    let probe_json = serde_json::to_string(&serde_json::json!({
+        "duration": duration,
+        "streams": streams,
+    }))
+    .unwrap_or_default();
+
+    upsert(conn, channel_id, path, meta.size, mtime, duration, &probe_json).await
+}
+
+/// Walk `prefix` once, descending into every sub-folder, and index every
+/// file found, reporting progress through `registry` as it goes.
+pub async fn scan_directory(
+    conn: &Pool<Sqlite>,
+    channel_id: i32,
+    store: Arc<dyn Store>,
+    registry: &JobRegistry,
+    prefix: &str,
+) -> Result<(), ServiceError> {
+    ensure_index_table(conn).await?;
+
+    registry
+        .set(JobStatus {
+            channel_id,
+            state: JobState::Running,
+            percent: 0.0,
+            current_file: None,
+        })
+        .await;
+
+    let mut stack = vec![prefix.to_string()];
+    let mut files = vec![];
+
+    while let Some(dir) = stack.pop() {
+        for entry in store.list(&dir).await? {
+            let full_path = format!("{}/{}", dir.trim_end_matches('/'), entry.name);
+
+            if entry.is_dir {
+                stack.push(full_path);
+            } else {
+                files.push(full_path);
+            }
+        }
+    }
+
+    let total = files.len().max(1);
+
+    for (i, file) in files.iter().enumerate() {
+        registry
+            .set(JobStatus {
+                channel_id,
+                state: JobState::Running,
+                percent: (i as f32 / total as f32) * 100.0,
+                current_file: Some(file.clone()),
+            })
+            .await;
+
+        if let Err(e) = index_file(conn, channel_id, &store, file).await {
+            error!("index {file}: {e}");
+        }
+    }
+
+    registry
+        .set(JobStatus {
+            channel_id,
+            state: JobState::Done,
+            percent: 100.0,
+            current_file: None,
+        })
+        .await;
+
+    Ok(())
+}
+
+lazy_static! {
+    /// Process-wide registry backing [`job_status`], and the guard that
+    /// keeps [`ensure_channel_indexed`] idempotent per channel.
+    static ref REGISTRY: Arc<JobRegistry> = Arc::new(JobRegistry::default());
+    static ref STARTED: Mutex<HashSet<i32>> = Mutex::new(HashSet::new());
+}
+
+/// Kick off an initial full scan of the channel's storage, and - for backends
+/// with a local filesystem presence - a [`watcher::watch_channel`] task that
+/// keeps the index incrementally fresh afterwards. Idempotent: only the
+/// first call for a given `channel_id` in this process actually starts
+/// anything, later calls (e.g. once per `browser()` request) are a no-op.
+pub async fn ensure_channel_indexed(conn: Pool<Sqlite>, channel_id: i32, store: Arc<dyn Store>) {
+    let mut started = STARTED.lock().await;
+
+    if !started.insert(channel_id) {
+        return;
+    }
+
+    drop(started);
+
+    let scan_conn = conn.clone();
+    let scan_store = store.clone();
+
+    tokio::spawn(async move {
+        if let Err(e) = scan_directory(&scan_conn, channel_id, scan_store, &REGISTRY, "").await {
+            error!("initial media scan for channel {channel_id}: {e}");
+        }
+    });
+
+    if let Some(root) = store.local_path("") {
+        tokio::spawn(async move {
+            if let Err(e) =
+                watcher::watch_channel(conn, channel_id, store, &root, REGISTRY.clone()).await
+            {
+                error!("media watcher for channel {channel_id}: {e}");
+            }
+        });
+    }
+}
+
+/// Current scan/watch progress for `channel_id`, for a status API route to
+/// surface to the UI.
+pub async fn job_status(channel_id: i32) -> Option<JobStatus> {
+    REGISTRY.get(channel_id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn registry_reports_none_for_unknown_channel() {
+        let registry = JobRegistry::default();
+
+        assert!(registry.get(42).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn registry_updates_existing_channel_in_place() {
+        let registry = JobRegistry::default();
+
+        registry
+            .set(JobStatus {
+                channel_id: 1,
+                state: JobState::Running,
+                percent: 10.0,
+                current_file: Some("a.mp4".into()),
+            })
+            .await;
+        registry
+            .set(JobStatus {
+                channel_id: 1,
+                state: JobState::Done,
+                percent: 100.0,
+                current_file: None,
+            })
+            .await;
+
+        let status = registry.get(1).await.unwrap();
+        assert_eq!(status.state, JobState::Done);
+        assert_eq!(status.percent, 100.0);
+    }
+}
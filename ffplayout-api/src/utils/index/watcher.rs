@@ -0,0 +1,80 @@
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use sqlx::{Pool, Sqlite};
+use tokio::sync::mpsc;
+
+use simplelog::*;
+
+use super::{index_file, invalidate, JobRegistry};
+use crate::utils::store::Store;
+
+/// Watch a channel's storage root for create/modify/delete events and keep
+/// the media index in sync incrementally, instead of relying solely on
+/// periodic full scans.
+pub async fn watch_channel(
+    conn: Pool<Sqlite>,
+    channel_id: i32,
+    store: Arc<dyn Store>,
+    root: &Path,
+    registry: Arc<JobRegistry>,
+) -> notify::Result<()> {
+    let (tx, mut rx) = mpsc::channel::<Event>(100);
+
+    let mut watcher = RecommendedWatcher::new(
+        move |res: notify::Result<Event>| {
+            if let Ok(event) = res {
+                let _ = tx.blocking_send(event);
+            }
+        },
+        notify::Config::default().with_poll_interval(Duration::from_secs(2)),
+    )?;
+
+    watcher.watch(root, RecursiveMode::Recursive)?;
+
+    // keep the watcher alive for the lifetime of this task
+    let _watcher = watcher;
+
+    while let Some(event) = rx.recv().await {
+        for path in event.paths {
+            // `index_file`/`invalidate` key rows on the same leading-slash
+            // form `scan_directory`/`browser` build their keys with; without
+            // it a watcher-driven update lands on a different row than the
+            // one the browser reads.
+            let rel = format!(
+                "/{}",
+                path.strip_prefix(root)
+                    .unwrap_or(&path)
+                    .to_string_lossy()
+                    .trim_start_matches('/')
+            );
+
+            match event.kind {
+                EventKind::Remove(_) => {
+                    if let Err(e) = invalidate(&conn, channel_id, &rel).await {
+                        error!("invalidate {rel}: {e}");
+                    }
+                }
+                EventKind::Create(_) | EventKind::Modify(_) => {
+                    if let Err(e) = index_file(&conn, channel_id, &store, &rel).await {
+                        error!("index {rel}: {e}");
+                    } else {
+                        registry
+                            .set(super::JobStatus {
+                                channel_id,
+                                state: super::JobState::Running,
+                                percent: 100.0,
+                                current_file: Some(rel),
+                            })
+                            .await;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    Ok(())
+}
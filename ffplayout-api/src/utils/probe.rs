@@ -0,0 +1,200 @@
+//! Media probing backend selection.
+//!
+//! By default this crate shells out to `ffprobe` (via
+//! [`ffplayout_lib::utils::MediaProbe`]), which is fine for occasional
+//! lookups but spawns one process per file when a whole folder is scanned.
+//! Building with the `libav-probe` feature switches to in-process
+//! `ffmpeg-next`/`ffmpeg-sys` bindings instead, at the cost of requiring
+//! ffmpeg's dev headers at build time.
+//!
+//! NOTE: `ffplayout_lib::utils::MediaProbe` itself is not part of this crate
+//! and isn't checked out here, so its constructor can't be switched to the
+//! libav backend directly; [`probe_media`]/[`ProbeInfo`] is this crate's own
+//! probe entry point and every caller in `ffplayout-api` (not just the file
+//! browser and media index) should go through it rather than constructing
+//! `MediaProbe` directly, so they all pick up the libav backend when it's
+//! enabled.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use futures_util::TryStreamExt;
+
+use crate::utils::errors::ServiceError;
+use crate::utils::store::Store;
+
+/// One elementary stream inside a probed container.
+#[derive(Debug, Clone, Default)]
+pub struct StreamInfo {
+    pub codec_type: String,
+    pub codec_name: String,
+    pub width: Option<i64>,
+    pub height: Option<i64>,
+}
+
+/// The probe output the rest of the crate needs: duration for the file
+/// browser/media index, plus per-stream codec/resolution info for playlist
+/// validation.
+#[derive(Debug, Clone, Default)]
+pub struct ProbeInfo {
+    pub duration: f64,
+    pub streams: Vec<StreamInfo>,
+}
+
+impl ProbeInfo {
+    pub fn video_stream(&self) -> Option<&StreamInfo> {
+        self.streams.iter().find(|s| s.codec_type == "video")
+    }
+
+    pub fn audio_stream(&self) -> Option<&StreamInfo> {
+        self.streams.iter().find(|s| s.codec_type == "audio")
+    }
+}
+
+/// Probe a media object reachable through `store`.
+///
+/// [`Store::local_path`] gives backends with a real filesystem presence
+/// (like `LocalStore`) a directly-openable path; backends without one (like
+/// `ObjectStore`) have no local file to hand ffprobe/libav, so this streams
+/// the object to a temporary file first and probes that instead.
+pub async fn probe_media(store: &Arc<dyn Store>, path: &str) -> Result<ProbeInfo, ServiceError> {
+    if let Some(local) = store.local_path(path) {
+        return probe(&local);
+    }
+
+    let tmp = tempfile::Builder::new()
+        .prefix("ffplayout-probe-")
+        .tempfile()
+        .map_err(ServiceError::from)?;
+    let tmp_path = tmp.path().to_path_buf();
+
+    let mut stream = store.get(path).await?;
+    let mut file = tokio::fs::File::create(&tmp_path).await?;
+
+    while let Some(chunk) = stream.try_next().await? {
+        tokio::io::AsyncWriteExt::write_all(&mut file, &chunk).await?;
+    }
+
+    drop(file);
+
+    probe(&tmp_path)
+}
+
+#[cfg(not(feature = "libav-probe"))]
+pub fn probe(path: &Path) -> Result<ProbeInfo, ServiceError> {
+    let probe = ffplayout_lib::utils::MediaProbe::new(path.to_string_lossy().as_ref())
+        .map_err(|e| ServiceError::BadRequest(e.to_string()))?;
+
+    let duration = probe
+        .format
+        .duration
+        .and_then(|d| d.parse().ok())
+        .unwrap_or_default();
+
+    let streams = probe
+        .streams
+        .into_iter()
+        .map(|s| StreamInfo {
+            codec_type: s.codec_type.unwrap_or_default(),
+            codec_name: s.codec_name.unwrap_or_default(),
+            width: s.width,
+            height: s.height,
+        })
+        .collect();
+
+    Ok(ProbeInfo { duration, streams })
+}
+
+#[cfg(feature = "libav-probe")]
+pub fn probe(path: &Path) -> Result<ProbeInfo, ServiceError> {
+    libav::probe(path)
+}
+
+#[cfg(feature = "libav-probe")]
+mod libav {
+    use std::path::Path;
+
+    use ffmpeg_next as ffmpeg;
+
+    use super::{ProbeInfo, StreamInfo};
+    use crate::utils::errors::ServiceError;
+
+    /// Open `path` with libav directly and read duration, stream types,
+    /// codecs and resolution, instead of spawning an `ffprobe` process for
+    /// every file.
+    pub fn probe(path: &Path) -> Result<ProbeInfo, ServiceError> {
+        ffmpeg::init().map_err(|e| ServiceError::BadRequest(e.to_string()))?;
+
+        let ctx =
+            ffmpeg::format::input(&path).map_err(|e| ServiceError::BadRequest(e.to_string()))?;
+
+        let duration = ctx.duration() as f64 / f64::from(ffmpeg::ffi::AV_TIME_BASE);
+
+        let streams = ctx
+            .streams()
+            .map(|stream| {
+                let params = stream.parameters();
+                let codec_type = match params.medium() {
+                    ffmpeg::media::Type::Video => "video",
+                    ffmpeg::media::Type::Audio => "audio",
+                    ffmpeg::media::Type::Subtitle => "subtitle",
+                    _ => "data",
+                }
+                .to_string();
+                let codec_name = params.id().name().to_string();
+
+                let (width, height) = ffmpeg::codec::context::Context::from_parameters(params)
+                    .ok()
+                    .and_then(|ctx| ctx.decoder().video().ok())
+                    .map(|video| (Some(video.width() as i64), Some(video.height() as i64)))
+                    .unwrap_or((None, None));
+
+                StreamInfo {
+                    codec_type,
+                    codec_name,
+                    width,
+                    height,
+                }
+            })
+            .collect();
+
+        Ok(ProbeInfo { duration, streams })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn probe_reports_error_for_missing_file() {
+        let result = probe(Path::new("/no/such/file-ffplayout-test.mp4"));
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn video_stream_picks_out_the_right_codec_type() {
+        let info = ProbeInfo {
+            duration: 12.5,
+            streams: vec![
+                StreamInfo {
+                    codec_type: "audio".into(),
+                    codec_name: "aac".into(),
+                    width: None,
+                    height: None,
+                },
+                StreamInfo {
+                    codec_type: "video".into(),
+                    codec_name: "h264".into(),
+                    width: Some(1920),
+                    height: Some(1080),
+                },
+            ],
+        };
+
+        let video = info.video_stream().unwrap();
+        assert_eq!(video.codec_name, "h264");
+        assert_eq!(video.width, Some(1920));
+    }
+}
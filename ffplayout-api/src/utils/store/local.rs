@@ -0,0 +1,282 @@
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures_util::stream::{BoxStream, StreamExt};
+use lazy_static::lazy_static;
+use relative_path::RelativePath;
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use tokio_util::io::ReaderStream;
+
+use simplelog::*;
+
+use super::{Entry, ObjectMeta, Store};
+use crate::utils::errors::ServiceError;
+
+lazy_static! {
+    pub static ref HOME_DIR: String = home::home_dir()
+        .unwrap_or("/home/h1wl3n2og".into()) // any random not existing folder
+        .as_os_str()
+        .to_string_lossy()
+        .to_string();
+}
+
+const FOLDER_WHITELIST: &[&str; 6] = &[
+    "/media",
+    "/mnt",
+    "/playlists",
+    "/tv-media",
+    "/usr/share/ffplayout",
+    "/var/lib/ffplayout",
+];
+
+/// Normalize absolut path
+///
+/// This function takes care, that it is not possible to break out from root_path.
+pub fn norm_abs_path(
+    root_path: &Path,
+    input_path: &str,
+) -> Result<(PathBuf, String, String), ServiceError> {
+    let path_relative = RelativePath::new(&root_path.to_string_lossy())
+        .normalize()
+        .to_string()
+        .replace("../", "");
+    let mut source_relative = RelativePath::new(input_path)
+        .normalize()
+        .to_string()
+        .replace("../", "");
+    let path_suffix = root_path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+
+    if input_path.starts_with(&*root_path.to_string_lossy())
+        || source_relative.starts_with(&path_relative)
+    {
+        source_relative = source_relative
+            .strip_prefix(&path_relative)
+            .and_then(|s| s.strip_prefix('/'))
+            .unwrap_or_default()
+            .to_string();
+    } else {
+        source_relative = source_relative
+            .strip_prefix(&path_suffix)
+            .and_then(|s| s.strip_prefix('/'))
+            .unwrap_or(&source_relative)
+            .to_string();
+    }
+
+    let path = &root_path.join(&source_relative);
+
+    if !FOLDER_WHITELIST.iter().any(|f| path.starts_with(f))
+        && !path.starts_with(&HOME_DIR.to_string())
+    {
+        return Err(ServiceError::Forbidden(
+            "Access forbidden: Folder cannot be opened.".to_string(),
+        ));
+    }
+
+    Ok((path.to_path_buf(), path_suffix, source_relative))
+}
+
+/// A [`Store`] backed by a POSIX filesystem rooted at `root`.
+///
+/// This is the historic ffplayout behavior: every key is jailed under `root`
+/// via [`norm_abs_path`] before any `tokio::fs` call touches it.
+pub struct LocalStore {
+    root: PathBuf,
+}
+
+impl LocalStore {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Jail `key` under this store's root, the same way the pre-`Store` code did.
+    pub fn resolve(&self, key: &str) -> Result<(PathBuf, String, String), ServiceError> {
+        norm_abs_path(&self.root, key)
+    }
+}
+
+#[async_trait]
+impl Store for LocalStore {
+    async fn list(&self, prefix: &str) -> Result<Vec<Entry>, ServiceError> {
+        let (path, ..) = self.resolve(prefix)?;
+        let mut read_dir = fs::read_dir(&path).await?;
+        let mut entries = vec![];
+
+        while let Some(child) = read_dir.next_entry().await? {
+            let name = child.file_name().to_string_lossy().to_string();
+
+            if name.starts_with('.') {
+                continue;
+            }
+
+            entries.push(Entry {
+                is_dir: child.metadata().await?.is_dir(),
+                name,
+            });
+        }
+
+        Ok(entries)
+    }
+
+    async fn head(&self, path: &str) -> Result<ObjectMeta, ServiceError> {
+        let (path, ..) = self.resolve(path)?;
+        let meta = fs::metadata(&path).await?;
+
+        Ok(ObjectMeta {
+            size: meta.len(),
+            modified: meta.modified().ok(),
+        })
+    }
+
+    async fn get(
+        &self,
+        path: &str,
+    ) -> Result<BoxStream<'static, Result<Bytes, ServiceError>>, ServiceError> {
+        let (path, ..) = self.resolve(path)?;
+        let file = fs::File::open(path).await?;
+
+        Ok(ReaderStream::new(file)
+            .map(|chunk| chunk.map_err(ServiceError::from))
+            .boxed())
+    }
+
+    async fn put(
+        &self,
+        path: &str,
+        stream: BoxStream<'static, Result<Bytes, ServiceError>>,
+    ) -> Result<(), ServiceError> {
+        let (path, ..) = self.resolve(path)?;
+
+        #[cfg(all(target_os = "linux", feature = "io-uring"))]
+        {
+            super::uring::write_file(path, stream).await
+        }
+
+        #[cfg(not(all(target_os = "linux", feature = "io-uring")))]
+        {
+            let mut stream = stream;
+            let mut file = fs::File::create(path).await?;
+
+            while let Some(chunk) = stream.next().await {
+                file.write_all(&chunk?).await?;
+            }
+
+            Ok(())
+        }
+    }
+
+    async fn rename(&self, src: &str, dst: &str) -> Result<(), ServiceError> {
+        let (src, ..) = self.resolve(src)?;
+        let (dst, ..) = self.resolve(dst)?;
+
+        if fs::rename(&src, &dst).await.is_err() {
+            fs::copy(&src, &dst).await?;
+            fs::remove_file(&src).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn create_folder(&self, path: &str) -> Result<(), ServiceError> {
+        let (path, ..) = self.resolve(path)?;
+        fs::create_dir_all(path).await?;
+
+        Ok(())
+    }
+
+    async fn delete(&self, path: &str) -> Result<(), ServiceError> {
+        let (path, ..) = self.resolve(path)?;
+
+        if path.is_dir() {
+            fs::remove_dir(&path).await.map_err(|e| {
+                error!("{e}");
+                ServiceError::BadRequest("Delete folder failed! (Folder must be empty)".into())
+            })
+        } else {
+            fs::remove_file(&path).await.map_err(|e| {
+                error!("{e}");
+                ServiceError::BadRequest("Delete file failed!".into())
+            })
+        }
+    }
+
+    async fn exists(&self, path: &str) -> bool {
+        match self.resolve(path) {
+            Ok((path, ..)) => path.exists(),
+            Err(_) => false,
+        }
+    }
+
+    async fn is_dir(&self, path: &str) -> bool {
+        match self.resolve(path) {
+            Ok((path, ..)) => path.is_dir(),
+            Err(_) => false,
+        }
+    }
+
+    fn local_path(&self, path: &str) -> Option<PathBuf> {
+        self.resolve(path).ok().map(|(path, ..)| path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::stream;
+
+    use super::*;
+
+    #[test]
+    fn norm_abs_path_jails_inside_whitelisted_root() {
+        let root = Path::new("/media/channel1");
+        let (path, ..) = norm_abs_path(root, "clip.mp4").unwrap();
+
+        assert_eq!(path, root.join("clip.mp4"));
+    }
+
+    #[test]
+    fn norm_abs_path_rejects_root_outside_whitelist() {
+        let err = norm_abs_path(Path::new("/srv/not-whitelisted"), "clip.mp4");
+
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn local_store_local_path_resolves_under_root() {
+        let store = LocalStore::new(PathBuf::from("/media/channel1"));
+
+        assert_eq!(
+            store.local_path("clip.mp4"),
+            Some(PathBuf::from("/media/channel1/clip.mp4"))
+        );
+    }
+
+    // `put`'s non-uring fallback path, exercised under `HOME_DIR` since
+    // `norm_abs_path` only allows `FOLDER_WHITELIST` entries or the home dir.
+    #[cfg(not(all(target_os = "linux", feature = "io-uring")))]
+    #[tokio::test]
+    async fn put_writes_every_chunk_of_the_stream() {
+        let root = PathBuf::from(HOME_DIR.to_string()).join("ffplayout-store-test");
+        std::fs::create_dir_all(&root).unwrap();
+        let store = LocalStore::new(root.clone());
+
+        let chunks: Vec<Result<Bytes, ServiceError>> =
+            vec![Ok(Bytes::from_static(b"hello ")), Ok(Bytes::from_static(b"world"))];
+        let stream = stream::iter(chunks).boxed();
+
+        store.put("greeting.txt", stream).await.unwrap();
+
+        let written = std::fs::read_to_string(root.join("greeting.txt")).unwrap();
+        assert_eq!(written, "hello world");
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,116 @@
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures_util::stream::BoxStream;
+use serde::{Deserialize, Serialize};
+
+use crate::utils::errors::ServiceError;
+
+pub mod local;
+pub mod object;
+#[cfg(all(target_os = "linux", feature = "io-uring"))]
+mod uring;
+
+pub use local::LocalStore;
+pub use object::ObjectStore;
+
+/// Which kind of storage backend a channel is configured with.
+///
+/// `Local` keeps the historic behavior of a POSIX filesystem rooted at
+/// `config.storage.path`. `Remote` talks to an S3/MinIO-style object store
+/// so channel media can live off-box.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StoreBackend {
+    #[default]
+    Local,
+    Remote,
+}
+
+/// One entry returned by [`Store::list`].
+#[derive(Debug, Clone, Serialize)]
+pub struct Entry {
+    pub name: String,
+    pub is_dir: bool,
+}
+
+/// Metadata returned by [`Store::head`].
+#[derive(Debug, Clone, Copy)]
+pub struct ObjectMeta {
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+}
+
+/// A storage backend for channel media.
+///
+/// Paths are opaque, backend-normalized keys rather than [`std::path::PathBuf`];
+/// it is up to each implementation to decide what "normalized" means (a jailed
+/// absolute filesystem path for [`LocalStore`], a `bucket/key` style prefix for
+/// [`ObjectStore`]).
+#[async_trait]
+pub trait Store: Send + Sync {
+    /// List the immediate children of `prefix`.
+    async fn list(&self, prefix: &str) -> Result<Vec<Entry>, ServiceError>;
+
+    /// Fetch metadata (size, modification time) for `path`.
+    async fn head(&self, path: &str) -> Result<ObjectMeta, ServiceError>;
+
+    /// Open `path` for reading as a byte stream.
+    async fn get(&self, path: &str) -> Result<BoxStream<'static, Result<Bytes, ServiceError>>, ServiceError>;
+
+    /// Write `stream` to `path`, creating or overwriting it.
+    async fn put(
+        &self,
+        path: &str,
+        stream: BoxStream<'static, Result<Bytes, ServiceError>>,
+    ) -> Result<(), ServiceError>;
+
+    /// Move/rename an object or folder from `src` to `dst`.
+    async fn rename(&self, src: &str, dst: &str) -> Result<(), ServiceError>;
+
+    /// Create an (empty) folder at `path`, if the backend has a folder concept.
+    async fn create_folder(&self, path: &str) -> Result<(), ServiceError>;
+
+    /// Delete the object or (empty) folder at `path`.
+    async fn delete(&self, path: &str) -> Result<(), ServiceError>;
+
+    /// Whether `path` currently exists.
+    async fn exists(&self, path: &str) -> bool;
+
+    /// Whether `path` is a folder rather than a single object.
+    async fn is_dir(&self, path: &str) -> bool;
+
+    /// The resolved, directly-openable local filesystem path for `path`, if
+    /// this backend has one. [`LocalStore`] returns the jailed absolute path
+    /// produced by [`local::norm_abs_path`]; backends with no local
+    /// filesystem presence (like [`ObjectStore`]) return `None`, and callers
+    /// that need a real file (probing, transcoding) must fetch the object
+    /// via [`Store::get`] instead.
+    fn local_path(&self, path: &str) -> Option<std::path::PathBuf> {
+        let _ = path;
+        None
+    }
+}
+
+/// Build the [`Store`] for a channel's configured backend.
+///
+/// `root` is the jailed local root for [`StoreBackend::Local`]; the remote
+/// fields are only consulted for [`StoreBackend::Remote`].
+pub fn build_store(
+    backend: &StoreBackend,
+    root: std::path::PathBuf,
+    remote_endpoint: Option<String>,
+    remote_bucket: Option<String>,
+    remote_region: Option<String>,
+) -> Arc<dyn Store> {
+    match backend {
+        StoreBackend::Local => Arc::new(LocalStore::new(root)),
+        StoreBackend::Remote => Arc::new(ObjectStore::new(
+            remote_endpoint.unwrap_or_default(),
+            remote_bucket.unwrap_or_default(),
+            remote_region.unwrap_or_default(),
+        )),
+    }
+}
@@ -0,0 +1,184 @@
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures_util::stream::{BoxStream, StreamExt, TryStreamExt};
+use s3::{creds::Credentials, Bucket, Region};
+
+use super::{Entry, ObjectMeta, Store};
+use crate::utils::errors::ServiceError;
+
+/// A [`Store`] backed by an S3/MinIO-compatible object store.
+///
+/// Keys are not jailed the way [`super::LocalStore`] jails filesystem paths;
+/// the bucket itself is the root, and "folders" are simulated via the
+/// `/`-delimited prefix convention common to S3-style APIs.
+pub struct ObjectStore {
+    bucket: Bucket,
+}
+
+impl ObjectStore {
+    pub fn new(endpoint: String, bucket: String, region: String) -> Self {
+        let creds = Credentials::default().unwrap_or_else(|_| Credentials {
+            access_key: None,
+            secret_key: None,
+            security_token: None,
+            session_token: None,
+            expiration: None,
+        });
+
+        let bucket = Bucket::new(
+            &bucket,
+            Region::Custom { region, endpoint },
+            creds,
+        )
+        .expect("build object store bucket handle")
+        .with_path_style();
+
+        Self { bucket }
+    }
+
+    fn key(path: &str) -> String {
+        path.trim_start_matches('/').to_string()
+    }
+}
+
+impl From<s3::error::S3Error> for ServiceError {
+    fn from(err: s3::error::S3Error) -> Self {
+        ServiceError::BadRequest(err.to_string())
+    }
+}
+
+#[async_trait]
+impl Store for ObjectStore {
+    async fn list(&self, prefix: &str) -> Result<Vec<Entry>, ServiceError> {
+        let prefix = Self::key(prefix);
+        let prefix = if prefix.is_empty() || prefix.ends_with('/') {
+            prefix
+        } else {
+            format!("{prefix}/")
+        };
+
+        let results = self
+            .bucket
+            .list(prefix.clone(), Some("/".to_string()))
+            .await?;
+
+        let mut entries = vec![];
+
+        for page in results {
+            for dir in page.common_prefixes.unwrap_or_default() {
+                let name = dir
+                    .prefix
+                    .trim_end_matches('/')
+                    .rsplit('/')
+                    .next()
+                    .unwrap_or_default()
+                    .to_string();
+                entries.push(Entry {
+                    name,
+                    is_dir: true,
+                });
+            }
+
+            for object in page.contents {
+                let name = object.key.rsplit('/').next().unwrap_or_default().to_string();
+
+                if name.is_empty() {
+                    continue;
+                }
+
+                entries.push(Entry {
+                    name,
+                    is_dir: false,
+                });
+            }
+        }
+
+        Ok(entries)
+    }
+
+    // NOTE: unlike `LocalStore::head`, this never fills in `modified` - the
+    // `s3`/rust-s3 head-object response gives `last_modified` back as an RFC
+    // 2822 string, and this crate has no date-parsing dependency to turn
+    // that into a `SystemTime`. `index_file`'s cache-invalidation therefore
+    // falls back to `size`-only comparison for remote-backed channels; a
+    // same-size overwrite of a remote object is not picked up, not even by a
+    // full `scan_directory`, which goes through the same `index_file` check.
+    async fn head(&self, path: &str) -> Result<ObjectMeta, ServiceError> {
+        let (head, _) = self.bucket.head_object(Self::key(path)).await?;
+
+        Ok(ObjectMeta {
+            size: head.content_length.unwrap_or_default() as u64,
+            modified: None,
+        })
+    }
+
+    async fn get(
+        &self,
+        path: &str,
+    ) -> Result<BoxStream<'static, Result<Bytes, ServiceError>>, ServiceError> {
+        let data = self.bucket.get_object(Self::key(path)).await?;
+
+        Ok(futures_util::stream::once(async move { Ok(Bytes::from(data.to_vec())) }).boxed())
+    }
+
+    async fn put(
+        &self,
+        path: &str,
+        mut stream: BoxStream<'static, Result<Bytes, ServiceError>>,
+    ) -> Result<(), ServiceError> {
+        let mut buf = Vec::new();
+
+        while let Some(chunk) = stream.try_next().await? {
+            buf.extend_from_slice(&chunk);
+        }
+
+        self.bucket.put_object(Self::key(path), &buf).await?;
+
+        Ok(())
+    }
+
+    async fn rename(&self, src: &str, dst: &str) -> Result<(), ServiceError> {
+        let data = self.bucket.get_object(Self::key(src)).await?;
+        self.bucket
+            .put_object(Self::key(dst), data.as_slice())
+            .await?;
+        self.bucket.delete_object(Self::key(src)).await?;
+
+        Ok(())
+    }
+
+    async fn create_folder(&self, _path: &str) -> Result<(), ServiceError> {
+        // S3-style object stores have no real folder concept; prefixes come
+        // into existence implicitly once an object is put under them.
+        Ok(())
+    }
+
+    async fn delete(&self, path: &str) -> Result<(), ServiceError> {
+        self.bucket.delete_object(Self::key(path)).await?;
+
+        Ok(())
+    }
+
+    async fn exists(&self, path: &str) -> bool {
+        self.bucket.head_object(Self::key(path)).await.is_ok()
+    }
+
+    async fn is_dir(&self, path: &str) -> bool {
+        let prefix = format!("{}/", Self::key(path).trim_end_matches('/'));
+
+        // `bucket.list` always yields at least one page, even for a prefix
+        // that matches nothing, so the page count alone can't tell a real
+        // "folder" from a non-existent path; a page only proves it's a
+        // folder once it actually carries an object or a sub-prefix.
+        match self.bucket.list(prefix, Some("/".to_string())).await {
+            Ok(pages) => pages.iter().any(|page| {
+                !page.contents.is_empty()
+                    || page
+                        .common_prefixes
+                        .as_ref()
+                        .is_some_and(|prefixes| !prefixes.is_empty())
+            }),
+            Err(_) => false,
+        }
+    }
+}